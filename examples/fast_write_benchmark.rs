@@ -7,7 +7,7 @@ pub fn main() {
         let messages = 1000;
         let mut nanos = 0u128;
         for _n in 0..messages {
-            count = count + 1;
+            count += 1;
             let now = std::time::Instant::now();
             tx.send(count);
             nanos += now.elapsed().as_nanos();
@@ -21,7 +21,7 @@ pub fn main() {
         let hundred_millis = std::time::Duration::from_millis(100);
         loop {
             let v = rx.recv();
-            if v == None {
+            if v.is_none() {
                 break;
             }
 
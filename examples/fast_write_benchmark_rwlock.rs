@@ -9,7 +9,7 @@ pub fn main() {
         let messages = 1000;
         let mut nanos = 0u128;
         for _n in 0..messages {
-            count = count + 1;
+            count += 1;
             let now = std::time::Instant::now();
             let mut v = lock.write().unwrap();
             *v = count;
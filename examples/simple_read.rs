@@ -8,7 +8,7 @@ pub fn main() {
         let ten_millis = std::time::Duration::from_millis(10);
 
         for _n in 0..50 {
-            count = count + 1;
+            count += 1;
             tx.send(count);
             std::thread::sleep(ten_millis);
         }
@@ -22,7 +22,7 @@ pub fn main() {
         loop {
             let v = rx1.recv();
             println!("reader 1: {:?}", v);
-            if v == Some(50) || v == None {
+            if v == Some(50) || v.is_none() {
                 break;
             }
             let wait_time: u64 = rng.gen_range(0..50);
@@ -37,7 +37,7 @@ pub fn main() {
     loop {
         let v = rx.recv();
         println!("reader 0: {:?}", v);
-        if v == Some(50) || v == None {
+        if v == Some(50) || v.is_none() {
             break;
         }
         let wait_time: u64 = rng.gen_range(0..50);
@@ -57,47 +57,108 @@
 //!       or Fedor Pikus's [CppCon 2017 presentation](https://www.youtube.com/watch?v=rxQ5K9lo034)
 //!       to learn more.
 
-use crossbeam::epoch::{pin, Atomic, Guard, Owned, Shared};
+use crossbeam::epoch::{pin, Atomic, Guard, Owned};
 
-use std::sync::atomic::Ordering;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+
+/// State shared between [`Sender`]s and their [`Receiver`]s.
+///
+/// `value` is the lockless RCU slot. Every publish bumps `generation` and signals
+/// `notify`; on the read side, only the blocking, change-aware paths wait on `notify`,
+/// and only those and [`Cache`] look at `generation`. `closed` is the single source of truth for "the last
+/// sender is gone": every read path (blocking or not) checks it instead of relying on
+/// `value` having been nulled out, since the slot itself is never cleared — see
+/// [`Sender`]'s `Drop` impl for why. `sender_count` tracks how many live `Sender`s share
+/// this `Inner`, so `closed` only flips once the last one is dropped.
+struct Inner<T> {
+    value: Atomic<T>,
+    generation: AtomicU64,
+    closed: AtomicBool,
+    notify: Mutex<()>,
+    condvar: Condvar,
+    sender_count: AtomicUsize,
+}
 
 /// Updates receivers with the newest value.
+///
+/// `Sender` is [`Clone`], so multiple producer threads can share one `Receiver`'s
+/// latest-value slot: `send` uses a compare-and-swap loop instead of an unconditional
+/// swap, and the slot is only closed out once every clone has been dropped.
 pub struct Sender<T> {
-    inner_tx: Arc<Atomic<T>>,
+    inner: Arc<Inner<T>>,
 }
 
 /// Clones or borrows the newest value from the sender.
 #[derive(Clone)]
 pub struct Receiver<T> {
-    inner_rx: Arc<Atomic<T>>,
+    inner: Arc<Inner<T>>,
+    // The last generation this receiver has observed, for `recv_changed`/`borrow_changed`.
+    last_seen: u64,
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        self.inner.sender_count.fetch_add(1, Ordering::Relaxed);
+        Sender {
+            inner: self.inner.clone(),
+        }
+    }
 }
 
 impl<T> Drop for Sender<T> {
     fn drop(&mut self) {
-        // Atomically swap the value inside with null.
+        // Only the last surviving sender tears down the slot; the others just go away.
+        if self.inner.sender_count.fetch_sub(1, Ordering::AcqRel) != 1 {
+            return;
+        }
+
+        // Deliberately leave `value` as-is: a receiver that's mid-`recv_changed` might
+        // still need to observe the last published value, and nulling the slot out here
+        // races with that read (the generation bump that announced the value and this
+        // drop can land in either order from a reader's perspective). `closed` below is
+        // what tells readers the sender is gone; the slot itself gets reclaimed once
+        // `Inner` itself is dropped (see its `Drop` impl), same as any other superseded
+        // value.
+        self.inner.closed.store(true, Ordering::Release);
+        let _lock = self.inner.notify.lock().unwrap();
+        self.inner.condvar.notify_all();
+    }
+}
+
+impl<T> Drop for Inner<T> {
+    fn drop(&mut self) {
+        // Nothing can load from `value` any more: `Inner` only drops once every
+        // `Sender`/`Receiver`/`Cache` sharing the `Arc` is gone. A `Borrow` holds no
+        // `Arc` and may still point at the value, so it's reclaimed through the epoch
+        // rather than dropped here.
         let guard = pin();
-        let v = self.inner_tx.swap(Shared::null(), Ordering::SeqCst, &guard);
-        // If we had a value in there, mark it for deletion
-        // as soon as all readers are done with it.
-        if !v.is_null() {
+        let current = self.value.load(Ordering::Acquire, &guard);
+        if !current.is_null() {
             unsafe {
-                guard.defer_destroy(v);
+                guard.defer_destroy(current);
             }
         }
-        // Optional, but useful since we probably don't drop senders until it's time to go:
-        // Flush the thread-local cache of deferred deletes.
-        guard.flush();
     }
 }
 
 /// Build a new [`Sender`] and [`Receiver`] pair, initialized to `v`.
 pub fn new<T>(v: T) -> (Sender<T>, Receiver<T>) {
-    let inner = Arc::new(Atomic::new(v));
+    let inner = Arc::new(Inner {
+        value: Atomic::new(v),
+        generation: AtomicU64::new(0),
+        closed: AtomicBool::new(false),
+        notify: Mutex::new(()),
+        condvar: Condvar::new(),
+        sender_count: AtomicUsize::new(1),
+    });
     let tx = Sender {
-        inner_tx: inner.clone(),
+        inner: inner.clone(),
+    };
+    let rx = Receiver {
+        inner,
+        last_seen: 0,
     };
-    let rx = Receiver { inner_rx: inner };
     (tx, rx)
 }
 
@@ -105,24 +166,89 @@ pub fn new<T>(v: T) -> (Sender<T>, Receiver<T>) {
 ///
 /// Useful for when there's no sane default value.
 pub fn empty<T>() -> (Sender<T>, Receiver<T>) {
-    let inner = Arc::new(Atomic::null());
+    let inner = Arc::new(Inner {
+        value: Atomic::null(),
+        generation: AtomicU64::new(0),
+        closed: AtomicBool::new(false),
+        notify: Mutex::new(()),
+        condvar: Condvar::new(),
+        sender_count: AtomicUsize::new(1),
+    });
     let tx = Sender {
-        inner_tx: inner.clone(),
+        inner: inner.clone(),
+    };
+    let rx = Receiver {
+        inner,
+        last_seen: 0,
     };
-    let rx = Receiver { inner_rx: inner };
     (tx, rx)
 }
 
 impl<T> Sender<T> {
-    /// Publish a new value to the matching [`Receiver`]s
+    /// Publish a new value to the matching [`Receiver`]s.
+    ///
+    /// Safe to call from multiple clones of the same `Sender` at once: internally this
+    /// is a `compare_exchange` loop, so two concurrent `send`s never stomp on each
+    /// other's publish.
     pub fn send(&mut self, v: T) {
         let guard = pin();
-        let prev = self.inner_tx.swap(Owned::new(v), Ordering::Release, &guard);
-        if !prev.is_null() {
+        let mut current = self.inner.value.load(Ordering::Acquire, &guard);
+        let mut new = Owned::new(v);
+        while let Err(e) =
+            self.inner
+                .value
+                .compare_exchange(current, new, Ordering::Release, Ordering::Relaxed, &guard)
+        {
+            current = e.current;
+            new = e.new;
+        }
+        if !current.is_null() {
             unsafe {
-                guard.defer_destroy(prev);
+                guard.defer_destroy(current);
             }
         }
+        self.notify_published();
+    }
+
+    /// Publishes a value derived from the current one, without a separate mutex to hold
+    /// it in between. `f` is handed `Some(&current)` (or `None` if nothing's been sent
+    /// yet) and returns the value to publish in its place.
+    ///
+    /// Safe to call from multiple clones of the same `Sender` at once, same as
+    /// [`send()`](Sender::send): internally this is a `compare_exchange` loop, so a
+    /// concurrent `send`/`rcu` that lands first just makes `f` re-run against the value
+    /// it published, instead of one publish silently clobbering the other.
+    pub fn rcu<F: FnMut(Option<&T>) -> T>(&mut self, mut f: F) {
+        let guard = pin();
+        let mut current = self.inner.value.load(Ordering::Acquire, &guard);
+        let mut new = Owned::new(f(unsafe { current.as_ref() }));
+        while let Err(e) =
+            self.inner
+                .value
+                .compare_exchange(current, new, Ordering::Release, Ordering::Relaxed, &guard)
+        {
+            current = e.current;
+            new = Owned::new(f(unsafe { current.as_ref() }));
+        }
+        if !current.is_null() {
+            unsafe {
+                guard.defer_destroy(current);
+            }
+        }
+        self.notify_published();
+    }
+
+    /// Bumps the generation counter and wakes anyone blocked in
+    /// `recv_changed`/`borrow_changed`, after a new value has already been swapped in.
+    ///
+    /// The lock (even though it guards no data of its own) is what gives us the
+    /// happens-before edge a waiter needs: it re-checks the generation while holding
+    /// the same mutex we're holding here, so a publish that lands between the waiter's
+    /// check and its `wait()` can't be missed.
+    fn notify_published(&self) {
+        self.inner.generation.fetch_add(1, Ordering::Release);
+        let _lock = self.inner.notify.lock().unwrap();
+        self.inner.condvar.notify_all();
     }
 }
 
@@ -153,8 +279,11 @@ impl<T> Receiver<T> {
     /// Just because you *can* hold onto this borrow indefinitely dones't mean you should.
     /// The [`Sender`] is presumably publishing new versions, making it increasingly stale!
     pub fn borrow(&self) -> Option<Borrow<T>> {
+        if self.inner.closed.load(Ordering::Acquire) {
+            return None;
+        }
         let guard = pin();
-        let shared = self.inner_rx.load_consume(&guard).as_raw(); // This one's for Paul.
+        let shared = self.inner.value.load_consume(&guard).as_raw(); // This one's for Paul.
         if shared.is_null() {
             None
         } else {
@@ -164,6 +293,73 @@ impl<T> Receiver<T> {
             })
         }
     }
+
+    /// Blocks until a value newer than the last one this `Receiver` observed is published,
+    /// then borrows it. Returns `None` once the [`Sender`] is dropped and no newer value
+    /// ever showed up.
+    ///
+    /// Unlike [`borrow()`](Receiver::borrow), this only touches the condvar when there's
+    /// nothing new to report. It also deliberately doesn't go through `borrow()` once a
+    /// fresh generation is seen: the `Sender` may have dropped (and hence closed) in the
+    /// instant between the generation bump and this read, and `borrow()`'s closed check
+    /// would then throw away a value that genuinely got published. So once a fresh
+    /// generation is seen, the value is loaded straight from the slot, with no closed check.
+    pub fn borrow_changed(&mut self) -> Option<Borrow<T>> {
+        loop {
+            let generation = self.inner.generation.load(Ordering::Acquire);
+            if generation > self.last_seen {
+                self.last_seen = generation;
+                let guard = pin();
+                let shared = self.inner.value.load_consume(&guard).as_raw();
+                return if shared.is_null() {
+                    None
+                } else {
+                    Some(Borrow {
+                        _guard: guard,
+                        shared,
+                    })
+                };
+            }
+
+            let lock = self.inner.notify.lock().unwrap();
+            // Re-check under the lock: a send() that landed between our load above and
+            // grabbing this lock would otherwise be missed, since it also locks `notify`
+            // (even just to notify) before anyone can be waiting on it again.
+            if self.inner.generation.load(Ordering::Acquire) > self.last_seen {
+                continue;
+            }
+            if self.inner.closed.load(Ordering::Acquire) {
+                return None;
+            }
+            let _lock = self.inner.condvar.wait(lock).unwrap();
+        }
+    }
+
+    /// Borrows a projection of the current value — e.g. one field out of a larger
+    /// published struct — without cloning or exposing the whole `T`.
+    ///
+    /// This holds the same epoch guard [`borrow()`](Receiver::borrow) does, just pointed
+    /// at whatever `f` returns instead of at the top-level value, so the result is just
+    /// as independent of the `Sender`'s and `Receiver`'s lifetimes.
+    pub fn borrow_map<U, F: Fn(&T) -> &U>(&self, f: F) -> Option<Borrow<U>> {
+        if self.inner.closed.load(Ordering::Acquire) {
+            return None;
+        }
+        let guard = pin();
+        let shared = self.inner.value.load_consume(&guard).as_raw();
+        if shared.is_null() {
+            None
+        } else {
+            // SAFETY: `shared` stays valid for as long as `guard` is held, and we carry
+            // `guard` along inside the returned `Borrow`, so the projection does too.
+            let live: &T = unsafe { &*shared };
+            let projected: *const U = f(live) as *const U;
+            Some(Borrow {
+                _guard: guard,
+                shared: projected,
+            })
+        }
+    }
 }
 
 impl<T: Clone> Receiver<T> {
@@ -171,16 +367,89 @@ impl<T: Clone> Receiver<T> {
     ///
     /// If cloning isn't cheap (or possible!) consider [`borrow()`](Receiver::borrow)
     pub fn recv(&self) -> Option<T> {
+        if self.inner.closed.load(Ordering::Acquire) {
+            return None;
+        }
         let guard = pin();
-        let v = self.inner_rx.load_consume(&guard); // memory_order_consume lives!
+        let v = self.inner.value.load_consume(&guard); // memory_order_consume lives!
         let inner_ref = unsafe { v.as_ref() };
-        match inner_ref {
-            Some(b) => Some(b.clone()),
-            None => None,
+        inner_ref.cloned()
+    }
+
+    /// Blocks until a value newer than the last one this `Receiver` observed is published,
+    /// then clones it. Returns `None` once the [`Sender`] is dropped and no newer value
+    /// ever showed up.
+    ///
+    /// See [`borrow_changed()`](Receiver::borrow_changed) for the non-cloning equivalent,
+    /// including why this doesn't just delegate to [`recv()`](Receiver::recv) once a
+    /// fresh generation is seen.
+    pub fn recv_changed(&mut self) -> Option<T> {
+        loop {
+            let generation = self.inner.generation.load(Ordering::Acquire);
+            if generation > self.last_seen {
+                self.last_seen = generation;
+                let guard = pin();
+                let v = self.inner.value.load_consume(&guard);
+                let inner_ref = unsafe { v.as_ref() };
+                return inner_ref.cloned();
+            }
+
+            let lock = self.inner.notify.lock().unwrap();
+            // Same missed-wakeup guard as `borrow_changed`: re-check under the lock.
+            if self.inner.generation.load(Ordering::Acquire) > self.last_seen {
+                continue;
+            }
+            if self.inner.closed.load(Ordering::Acquire) {
+                return None;
+            }
+            let _lock = self.inner.condvar.wait(lock).unwrap();
         }
     }
 }
 
+/// Wraps a [`Receiver`] for readers that poll in a tight loop but only occasionally see
+/// new data, so repeated [`access()`](Cache::access) calls skip both the epoch `pin()`
+/// and the clone whenever the writer's been idle.
+pub struct Cache<T: Clone> {
+    receiver: Receiver<T>,
+    cached: Option<T>,
+    seen_generation: Option<u64>,
+}
+
+impl<T: Clone> Cache<T> {
+    /// Wrap a [`Receiver`] in a `Cache`.
+    pub fn new(receiver: Receiver<T>) -> Self {
+        Cache {
+            receiver,
+            cached: None,
+            seen_generation: None,
+        }
+    }
+
+    /// Returns the latest value, cloning only if the generation has advanced since the
+    /// last `access()`. If the generation hasn't moved, this is just a reference into
+    /// the existing cached clone — no `pin()`, no `load_consume`, no `clone()`.
+    ///
+    /// Once the [`Sender`] is dropped this returns `None` (and drops the cached value),
+    /// same as [`Receiver::recv`] — a poll loop waiting for close to show up won't keep
+    /// spinning on a stale cached value forever.
+    pub fn access(&mut self) -> Option<&T> {
+        if self.receiver.inner.closed.load(Ordering::Acquire) {
+            self.cached = None;
+            self.seen_generation = None;
+            return None;
+        }
+        let current_generation = self.receiver.inner.generation.load(Ordering::Acquire);
+        if self.seen_generation != Some(current_generation) {
+            let guard = pin();
+            let v = self.receiver.inner.value.load_consume(&guard);
+            self.cached = unsafe { v.as_ref() }.cloned();
+            self.seen_generation = Some(current_generation);
+        }
+        self.cached.as_ref()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -220,6 +489,223 @@ mod tests {
         assert_eq!(*b, 42);
     }
 
+    #[test]
+    fn recv_changed_blocks_until_send() {
+        let (mut tx, mut rx) = new::<u32>(0);
+
+        let t = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            tx.send(1);
+        });
+
+        // No change has happened yet, so this blocks instead of spinning.
+        assert_eq!(rx.recv_changed(), Some(1));
+
+        t.join().expect("writer didn't close cleanly");
+    }
+
+    #[test]
+    fn recv_changed_reaches_last_send() {
+        let (mut tx, mut rx) = new::<u32>(0);
+
+        let t = std::thread::spawn(move || {
+            for n in 1..=50 {
+                tx.send(n);
+            }
+        });
+
+        let mut last = 0;
+        while let Some(v) = rx.recv_changed() {
+            last = v;
+            if last == 50 {
+                break;
+            }
+        }
+        assert_eq!(last, 50);
+
+        t.join().expect("writer didn't close cleanly");
+    }
+
+    #[test]
+    fn recv_changed_returns_none_once_closed() {
+        let (tx, mut rx) = new::<u32>(42);
+        drop(tx);
+        assert_eq!(rx.recv_changed(), None);
+    }
+
+    #[test]
+    fn borrow_changed_blocks_until_send() {
+        let (mut tx, mut rx) = new::<u32>(0);
+
+        let t = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            tx.send(7);
+        });
+
+        let b = rx.borrow_changed().expect("expected a value, not a close");
+        assert_eq!(*b, 7);
+
+        t.join().expect("writer didn't close cleanly");
+    }
+
+    #[test]
+    fn rcu_updates_in_place() {
+        let (mut tx, rx) = new::<Vec<u32>>(vec![1, 2, 3]);
+
+        tx.rcu(|current| {
+            let mut v = current.expect("should have an initial value").clone();
+            v.push(4);
+            v
+        });
+
+        assert_eq!(rx.recv(), Some(vec![1, 2, 3, 4]));
+    }
+
+    #[test]
+    fn rcu_on_empty_sees_none() {
+        let (mut tx, rx) = empty::<u32>();
+
+        tx.rcu(|current| {
+            assert_eq!(current, None);
+            1
+        });
+
+        assert_eq!(rx.recv(), Some(1));
+    }
+
+    #[test]
+    fn rcu_wakes_recv_changed() {
+        let (mut tx, mut rx) = new::<u32>(0);
+
+        let t = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            tx.rcu(|current| current.copied().unwrap_or(0) + 1);
+        });
+
+        assert_eq!(rx.recv_changed(), Some(1));
+
+        t.join().expect("writer didn't close cleanly");
+    }
+
+    #[test]
+    fn receiver_stays_live_until_last_sender_clone_dropped() {
+        let (tx, rx) = new::<u32>(42);
+        let tx2 = tx.clone();
+
+        assert_eq!(rx.recv(), Some(42));
+
+        drop(tx);
+        // tx2 is still around, so the slot isn't torn down yet.
+        assert_eq!(rx.recv(), Some(42));
+
+        drop(tx2);
+        assert_eq!(rx.recv(), None);
+    }
+
+    #[test]
+    fn concurrent_senders_close_once_all_dropped() {
+        let (tx, rx) = empty::<u32>();
+        let mut senders: Vec<Sender<u32>> = (0..4).map(|_| tx.clone()).collect();
+        drop(tx);
+
+        let threads: Vec<_> = senders
+            .drain(..)
+            .enumerate()
+            .map(|(i, mut sender)| {
+                std::thread::spawn(move || {
+                    for n in 0..25 {
+                        sender.send(i as u32 * 100 + n);
+                    }
+                })
+            })
+            .collect();
+
+        for t in threads {
+            t.join().expect("sender thread didn't close cleanly");
+        }
+
+        // All four senders are gone, so the slot is torn down and reads go back to None.
+        assert_eq!(rx.recv(), None);
+    }
+
+    #[test]
+    fn cache_skips_clone_when_idle() {
+        let (mut tx, rx) = new::<u32>(1);
+        let mut cache = Cache::new(rx);
+
+        assert_eq!(cache.access(), Some(&1));
+        // No new send happened, so this should just hand back the same cached value.
+        assert_eq!(cache.access(), Some(&1));
+
+        tx.send(2);
+        assert_eq!(cache.access(), Some(&2));
+    }
+
+    #[test]
+    fn cache_on_empty_receiver() {
+        let (mut tx, rx) = empty::<u32>();
+        let mut cache = Cache::new(rx);
+
+        assert_eq!(cache.access(), None);
+
+        tx.send(5);
+        assert_eq!(cache.access(), Some(&5));
+    }
+
+    #[test]
+    fn cache_observes_close() {
+        let (tx, rx) = new::<u32>(1);
+        let mut cache = Cache::new(rx);
+
+        assert_eq!(cache.access(), Some(&1));
+
+        drop(tx);
+        // Closing doesn't bump the generation, so without a dedicated check `access()`
+        // would otherwise keep handing back the stale cached value forever.
+        assert_eq!(cache.access(), None);
+    }
+
+    #[test]
+    fn borrow_map_projects_a_field() {
+        struct Config {
+            name: String,
+            retries: u32,
+        }
+
+        let (mut tx, rx) = new(Config {
+            name: "primary".to_string(),
+            retries: 3,
+        });
+
+        let name = rx
+            .borrow_map(|c| &c.name)
+            .expect("expected a value, not an empty borrow");
+        assert_eq!(&*name, "primary");
+
+        let retries = rx
+            .borrow_map(|c| &c.retries)
+            .expect("expected a value, not an empty borrow");
+        assert_eq!(*retries, 3);
+
+        // The projected borrow keeps the whole Config alive independent of new sends...
+        tx.send(Config {
+            name: "secondary".to_string(),
+            retries: 5,
+        });
+        assert_eq!(*retries, 3);
+        assert_eq!(&*name, "primary");
+
+        // ...and independent of the sender going away entirely.
+        drop(tx);
+        assert_eq!(*retries, 3);
+    }
+
+    #[test]
+    fn borrow_map_on_empty_receiver() {
+        let (_tx, rx) = empty::<(u32, u32)>();
+        assert!(rx.borrow_map(|(a, _)| a).is_none());
+    }
+
     #[test]
     fn one_writer_one_reader_random_waits() {
         let (mut tx, rx) = new::<u32>(0);
@@ -229,7 +715,7 @@ mod tests {
             let ten_millis = std::time::Duration::from_millis(10);
 
             for _n in 0..50 {
-                count = count + 1;
+                count += 1;
                 tx.send(count);
                 std::thread::sleep(ten_millis);
             }
@@ -239,7 +725,7 @@ mod tests {
 
         loop {
             let v = rx.recv();
-            if v == Some(50) || v == None {
+            if v == Some(50) || v.is_none() {
                 break;
             }
             let wait_time: u64 = rng.gen_range(0..50);
@@ -259,7 +745,7 @@ mod tests {
             let ten_millis = std::time::Duration::from_millis(10);
 
             for _n in 0..50 {
-                count = count + 1;
+                count += 1;
                 tx.send(count);
                 std::thread::sleep(ten_millis);
             }